@@ -0,0 +1,261 @@
+//! A declarative description of the configuration keys `git` itself understands, used to
+//! validate a [`File`] against a single, central source of truth instead of failing lazily at
+//! each stringly-typed call site.
+use std::borrow::Cow;
+
+use bstr::BStr;
+
+use crate::{File, Integer};
+
+/// The kind of value a [`Key`] is expected to hold.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ValueType {
+    /// A boolean, e.g. `true`, `false`, `yes`, `no`, or an implicit `true` for a bare key.
+    Boolean,
+    /// An integer, optionally suffixed with `k`, `m` or `g`.
+    Integer,
+    /// An arbitrary string.
+    String,
+}
+
+/// A single, well-known configuration key within a [`Section`].
+pub struct Key {
+    /// The key's name, e.g. `"filemode"` for `core.filemode`.
+    pub name: &'static str,
+    /// The name of the [`Section`] this key belongs to, e.g. `"core"` for `core.filemode`.
+    pub section: &'static str,
+    /// The type of value this key is expected to hold.
+    pub value_type: ValueType,
+    /// An additional check run on top of the basic type check, for keys whose valid values are a
+    /// subset of their type, like `core.repositoryformatversion` only accepting `0` or `1`.
+    pub validate: Option<fn(&BStr) -> Result<(), String>>,
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key")
+            .field("name", &self.name)
+            .field("section", &self.section)
+            .field("value_type", &self.value_type)
+            .finish()
+    }
+}
+
+impl Key {
+    /// Look up this key as a boolean in `file`, resolving its own section.
+    ///
+    /// `subsection` is the file's own runtime data (e.g. the remote name for
+    /// `remote.<name>.url`-style keys) and so can't be resolved from the schema alone; pass
+    /// `None` for keys whose [`Section::requires_subsection`] is `false`.
+    ///
+    /// Returns `None` if the key isn't present at all.
+    pub fn try_into_boolean(&self, file: &File<'_>, subsection: Option<&str>) -> Option<Result<bool, crate::value::Error>> {
+        file.boolean(self.section, subsection, self.name)
+    }
+
+    /// Look up this key as an integer in `file`, resolving its own section.
+    ///
+    /// See [`Key::try_into_boolean()`] for how `subsection` is resolved.
+    ///
+    /// Returns `None` if the key isn't present at all.
+    pub fn try_into_integer(&self, file: &File<'_>, subsection: Option<&str>) -> Option<Result<Integer, crate::value::Error>> {
+        file.try_value::<Integer>(self.section, subsection, self.name)
+    }
+
+    /// Look up this key as a string in `file`, resolving its own section.
+    ///
+    /// See [`Key::try_into_boolean()`] for how `subsection` is resolved.
+    ///
+    /// Unlike [`Key::try_into_boolean()`] and [`Key::try_into_integer()`], this cannot fail: any
+    /// raw value is already a valid string, so there's no parsing step that could produce a
+    /// [`value::Error`](crate::value::Error).
+    ///
+    /// Returns `None` if the key isn't present at all.
+    pub fn try_into_string<'a>(&self, file: &'a File<'a>, subsection: Option<&str>) -> Option<Cow<'a, BStr>> {
+        file.string(self.section, subsection, self.name)
+    }
+}
+
+/// A `git` configuration section, like `core` or `remote.<name>`, together with the keys it's
+/// known to accept.
+#[derive(Debug)]
+pub struct Section {
+    /// The section's name, e.g. `"core"` or `"remote"`.
+    pub name: &'static str,
+    /// Whether entries of this section require a subsection, like `remote.<name>.url` does.
+    pub requires_subsection: bool,
+    /// The keys known to be valid within this section.
+    pub keys: &'static [Key],
+}
+
+/// A full description of the sections and keys `git` itself understands, used to validate a
+/// [`File`] against it with [`File::validate()`](crate::File::validate).
+#[derive(Debug, Default)]
+pub struct Schema {
+    /// The sections making up this schema.
+    pub sections: Vec<Section>,
+}
+
+/// A single violation found while validating a [`File`] against a [`Schema`].
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ValidationError {
+    /// A key was present whose value didn't have the expected type.
+    #[error("{section}.{key} expected a {expected:?} value but got {actual:?}")]
+    UnexpectedType {
+        /// The fully qualified section the key was found in, e.g. `remote.origin`.
+        section: String,
+        /// The key's name.
+        key: &'static str,
+        /// The type the schema expects for this key.
+        expected: ValueType,
+        /// The offending, raw value.
+        actual: bstr::BString,
+    },
+    /// A key was present whose value failed its extra, key-specific validation.
+    #[error("{section}.{key} is invalid: {reason}")]
+    InvalidValue {
+        /// The fully qualified section the key was found in, e.g. `core`.
+        section: String,
+        /// The key's name.
+        key: &'static str,
+        /// Why the value was rejected.
+        reason: String,
+    },
+}
+
+impl Schema {
+    /// The schema describing the subset of `git`'s own configuration keys this crate currently
+    /// knows about.
+    pub fn git() -> Self {
+        Schema {
+            sections: vec![
+                Section {
+                    name: "core",
+                    requires_subsection: false,
+                    keys: &[
+                        Key {
+                            name: "filemode",
+                            section: "core",
+                            value_type: ValueType::Boolean,
+                            validate: None,
+                        },
+                        Key {
+                            name: "bare",
+                            section: "core",
+                            value_type: ValueType::Boolean,
+                            validate: None,
+                        },
+                        Key {
+                            name: "repositoryformatversion",
+                            section: "core",
+                            value_type: ValueType::Integer,
+                            validate: Some(|value| match value.to_str().ok().and_then(|v| v.parse::<u8>().ok()) {
+                                Some(0) | Some(1) => Ok(()),
+                                _ => Err(format!("expected '0' or '1', got {:?}", value)),
+                            }),
+                        },
+                        Key {
+                            name: "autocrlf",
+                            section: "core",
+                            value_type: ValueType::String,
+                            validate: Some(|value| match value.to_str() {
+                                Ok("true") | Ok("false") | Ok("input") => Ok(()),
+                                _ => Err(format!("expected 'true', 'false' or 'input', got {:?}", value)),
+                            }),
+                        },
+                    ],
+                },
+                Section {
+                    name: "remote",
+                    requires_subsection: true,
+                    keys: &[Key {
+                        name: "url",
+                        section: "remote",
+                        value_type: ValueType::String,
+                        validate: None,
+                    }],
+                },
+            ],
+        }
+    }
+}
+
+impl<'a> File<'a> {
+    /// Validate all entries present in this file against `schema`, returning every violation
+    /// found.
+    ///
+    /// Keys that aren't part of `schema` are ignored; this only reports *known* keys whose value
+    /// doesn't match what `schema` expects of them.
+    pub fn validate(&self, schema: &Schema) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for section in &schema.sections {
+            for subsection in self.subsections_for(section) {
+                let qualified = match &subsection {
+                    Some(name) => format!("{}.{}", section.name, name),
+                    None => section.name.to_owned(),
+                };
+                for key in section.keys {
+                    let Some(value) = self.try_value::<Cow<'_, BStr>>(section.name, subsection.as_deref(), key.name) else {
+                        continue;
+                    };
+                    if let Err(error) = validate_value(key, &value) {
+                        errors.push(match error {
+                            Violation::UnexpectedType => ValidationError::UnexpectedType {
+                                section: qualified.clone(),
+                                key: key.name,
+                                expected: key.value_type,
+                                actual: value.into_owned().into(),
+                            },
+                            Violation::InvalidValue(reason) => ValidationError::InvalidValue {
+                                section: qualified.clone(),
+                                key: key.name,
+                                reason,
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// The subsections to check `section` against: just `None` if it doesn't require one, or
+    /// every subsection actually present in the file otherwise.
+    fn subsections_for(&self, section: &Section) -> Vec<Option<String>> {
+        if !section.requires_subsection {
+            return vec![None];
+        }
+        self.sections_by_name(section.name)
+            .into_iter()
+            .flatten()
+            .filter_map(|section| section.subsection_name().map(|name| Some(name.to_string())))
+            .collect()
+    }
+}
+
+enum Violation {
+    UnexpectedType,
+    InvalidValue(String),
+}
+
+fn validate_value(key: &Key, value: &BStr) -> Result<(), Violation> {
+    match key.value_type {
+        ValueType::Boolean => {
+            if crate::Boolean::try_from(value).is_err() {
+                return Err(Violation::UnexpectedType);
+            }
+        }
+        ValueType::Integer => {
+            if Integer::try_from(value).is_err() {
+                return Err(Violation::UnexpectedType);
+            }
+        }
+        ValueType::String => {}
+    }
+
+    if let Some(validate) = key.validate {
+        validate(value).map_err(Violation::InvalidValue)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use git_config::schema::{Schema, ValueType};
+use git_config::File;
+
+#[test]
+fn validate_accepts_well_formed_known_keys() -> crate::Result {
+    let config = r#"
+        [core]
+            filemode = true
+            repositoryformatversion = 0
+        [remote "origin"]
+            url = git@github.com:Byron/gitoxide.git
+    "#;
+
+    let file = File::try_from(config)?;
+    assert_eq!(file.validate(&Schema::git()), Vec::new(), "nothing to complain about here");
+
+    Ok(())
+}
+
+#[test]
+fn validate_reports_keys_with_the_wrong_type() -> crate::Result {
+    let config = r#"
+        [core]
+            bare = not-a-bool
+    "#;
+
+    let file = File::try_from(config)?;
+    let errors = file.validate(&Schema::git());
+    assert_eq!(errors.len(), 1);
+    assert!(
+        matches!(
+            &errors[0],
+            git_config::schema::ValidationError::UnexpectedType { key, expected, .. }
+            if *key == "bare" && *expected == ValueType::Boolean
+        ),
+        "got {:?}",
+        errors[0]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_reports_keys_that_fail_their_own_check() -> crate::Result {
+    let config = r#"
+        [core]
+            repositoryformatversion = 2
+    "#;
+
+    let file = File::try_from(config)?;
+    let errors = file.validate(&Schema::git());
+    assert_eq!(errors.len(), 1);
+    assert!(
+        matches!(
+            &errors[0],
+            git_config::schema::ValidationError::InvalidValue { key, .. } if *key == "repositoryformatversion"
+        ),
+        "got {:?}",
+        errors[0]
+    );
+
+    Ok(())
+}
+
+fn find<'a>(schema: &'a Schema, section: &str, key: &str) -> &'a git_config::schema::Key {
+    schema
+        .sections
+        .iter()
+        .find(|s| s.name == section)
+        .and_then(|s| s.keys.iter().find(|k| k.name == key))
+        .expect("present in Schema::git()")
+}
+
+#[test]
+fn try_into_boolean_resolves_its_own_section() -> crate::Result {
+    let file = File::try_from("[core]\n  filemode = false")?;
+    let schema = Schema::git();
+    let key = find(&schema, "core", "filemode");
+
+    assert_eq!(key.try_into_boolean(&file, None).expect("present")?, false);
+    assert!(key.try_into_boolean(&file, Some("nonexistent-subsection")).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn try_into_integer_resolves_its_own_section() -> crate::Result {
+    let file = File::try_from("[core]\n  repositoryformatversion = 1")?;
+    let schema = Schema::git();
+    let key = find(&schema, "core", "repositoryformatversion");
+
+    assert_eq!(key.try_into_integer(&file, None).expect("present")?.value, 1);
+
+    Ok(())
+}
+
+#[test]
+fn try_into_string_resolves_its_own_section_and_subsection() {
+    let file = File::try_from(r#"[remote "origin"]
+  url = git@github.com:Byron/gitoxide.git
+"#)
+    .expect("valid config");
+    let schema = Schema::git();
+    let key = find(&schema, "remote", "url");
+
+    assert_eq!(
+        key.try_into_string(&file, Some("origin")).expect("present").as_ref(),
+        "git@github.com:Byron/gitoxide.git"
+    );
+    assert!(key.try_into_string(&file, Some("upstream")).is_none());
+}
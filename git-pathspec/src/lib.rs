@@ -0,0 +1,75 @@
+//! A crate for matching paths against git's pathspec patterns.
+#![forbid(unsafe_code, rust_2018_idioms)]
+#![deny(missing_docs)]
+
+use bitflags::bitflags;
+use bstr::BString;
+
+pub mod parse;
+pub use parse::{parse, Error};
+
+pub mod search;
+pub use search::{Match, Search};
+
+bitflags! {
+    /// Flags to represent 'magic signature' which may be attached to any pathspec.
+    #[derive(Default)]
+    pub struct MagicSignature: u32 {
+        /// Matches a pathspec from the root of the repository, ignoring the current working directory.
+        const TOP = 1 << 0;
+        /// Matches in a case-insensitive way.
+        const ICASE = 1 << 1;
+        /// Excludes the pattern from the results, overriding any other matching pattern.
+        const EXCLUDE = 1 << 2;
+    }
+}
+
+/// How the `path` of a [`Pattern`] is applied to candidate paths.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SearchMode {
+    /// Expand `*` and `?` using shell-glob semantics, allowing them to match across path separators.
+    ShellGlob,
+    /// Expand `*` and `?` using `git`'s pathname-aware wildmatch semantics, where only `**` crosses
+    /// path separators.
+    PathAwareGlob,
+    /// Match `path` byte-for-byte, without interpreting any special characters.
+    Literal,
+}
+
+impl Default for SearchMode {
+    fn default() -> Self {
+        SearchMode::ShellGlob
+    }
+}
+
+/// The defaults applied to every [`Pattern`] before its own magic is parsed, capturing `git`'s
+/// pathspec-related environment overrides.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Defaults {
+    /// The signature every pattern starts out with.
+    pub signature: MagicSignature,
+    /// The search mode every pattern starts out with, unless overridden by its own magic.
+    pub search_mode: SearchMode,
+    /// If `true`, mirrors `GIT_LITERAL_PATHSPECS`: every pathspec is treated as a literal path and
+    /// its magic, if any, is never parsed.
+    pub literal: bool,
+}
+
+/// A parsed pathspec pattern, as it would be used to match against paths in a worktree or index.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Pattern {
+    /// The path as given by the pattern, with any in-string magic already stripped off.
+    pub path: BString,
+    /// The magic signature controlling how this pattern matches.
+    pub signature: MagicSignature,
+    /// How `path` is interpreted when matching against candidate paths.
+    pub search_mode: SearchMode,
+    /// Attributes that a path has to match to be selected by this pattern.
+    pub attributes: Vec<(BString, git_attributes::State)>,
+    /// The amount of leading bytes of `path` that correspond to the directory the pathspec was
+    /// given relative to, as recorded by the `prefix:<n>` magic keyword.
+    ///
+    /// `git` emits this when normalizing a pathspec given from within a subdirectory, so that the
+    /// original, relative form of the spec can be reconstructed later.
+    pub prefix: Option<usize>,
+}
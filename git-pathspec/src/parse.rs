@@ -1,7 +1,7 @@
 use bstr::{BString, ByteSlice};
 use git_attributes::parse::Iter;
 
-use crate::{MagicSignature, Pattern, SearchMode};
+use crate::{Defaults, MagicSignature, Pattern, SearchMode};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -21,36 +21,102 @@ pub enum Error {
     IncompatibleSearchModes,
     #[error("Only one attribute specification is allowed in the same pathspec")]
     MultipleAttributeSpecifications,
+    #[error("'glob' pathspecs (GIT_GLOB_PATHSPECS) and 'noglob' pathspecs (GIT_NOGLOB_PATHSPECS) cannot both be forced by the environment")]
+    IncompatibleEnvironmentSearchModes,
+    #[error("Found {:?} after 'prefix:', which is not a valid number", found_value)]
+    InvalidPrefixValue { found_value: BString },
+    #[error("Attribute value contains disallowed character {:?}", *character as char)]
+    InvalidAttributeValue { character: u8 },
+    #[error("Attribute value ends with a trailing, unescaped '\\'")]
+    TrailingEscapeCharacter,
 }
 
 impl Pattern {
+    /// Parse a pattern from `input`, applying no environment-based defaults.
+    ///
+    /// Use [`parse()`] to honor `git`'s `GIT_*_PATHSPECS` environment overrides.
     pub fn from_bytes(input: &[u8]) -> Result<Self, Error> {
-        if input.is_empty() {
-            return Err(Error::EmptyString);
-        }
+        parse(input, Defaults::default())
+    }
+}
 
-        let mut p = Pattern {
-            path: BString::default(),
-            signature: MagicSignature::empty(),
-            search_mode: SearchMode::ShellGlob,
+/// Parse a pattern from `input`, seeding it with `defaults` before applying its own magic.
+pub fn parse(input: &[u8], defaults: Defaults) -> Result<Pattern, Error> {
+    if input.is_empty() {
+        return Err(Error::EmptyString);
+    }
+
+    if defaults.literal {
+        return Ok(Pattern {
+            path: BString::from(input),
+            signature: defaults.signature,
+            search_mode: SearchMode::Literal,
             attributes: Vec::new(),
-        };
+            prefix: None,
+        });
+    }
 
-        let mut cursor = 0;
-        if input.first() == Some(&b':') {
+    let mut p = Pattern {
+        path: BString::default(),
+        signature: defaults.signature,
+        search_mode: defaults.search_mode,
+        attributes: Vec::new(),
+        prefix: None,
+    };
+
+    let mut cursor = 0;
+    if input.first() == Some(&b':') {
+        cursor += 1;
+        p.signature |= parse_short_keywords(input, &mut cursor)?;
+        if let Some(b'(') = input.get(cursor) {
             cursor += 1;
-            p.signature |= parse_short_keywords(input, &mut cursor)?;
-            if let Some(b'(') = input.get(cursor) {
-                cursor += 1;
-                let pat = parse_long_keywords(input, &mut cursor)?;
-                p.search_mode = pat.search_mode;
-                p.attributes = pat.attributes;
-                p.signature |= pat.signature;
-            }
+            let pat = parse_long_keywords(input, &mut cursor, p.search_mode)?;
+            p.search_mode = pat.search_mode;
+            p.attributes = pat.attributes;
+            p.signature |= pat.signature;
+            p.prefix = pat.prefix;
+        }
+    }
+
+    p.path = BString::from(&input[cursor..]);
+    Ok(p)
+}
+
+impl Defaults {
+    /// Derive the defaults every pathspec should be seeded with from `git`'s own environment
+    /// overrides: `GIT_LITERAL_PATHSPECS`, `GIT_GLOB_PATHSPECS`, `GIT_NOGLOB_PATHSPECS` and
+    /// `GIT_ICASE_PATHSPECS`.
+    pub fn from_environment() -> Result<Self, Error> {
+        let mut defaults = Self::default();
+
+        let literal = is_env_flag_set("GIT_LITERAL_PATHSPECS");
+        let glob = is_env_flag_set("GIT_GLOB_PATHSPECS");
+        let noglob = is_env_flag_set("GIT_NOGLOB_PATHSPECS");
+
+        if glob && noglob {
+            return Err(Error::IncompatibleEnvironmentSearchModes);
         }
 
-        p.path = BString::from(&input[cursor..]);
-        Ok(p)
+        defaults.literal = literal;
+        if glob {
+            defaults.search_mode = SearchMode::PathAwareGlob;
+        } else if noglob {
+            defaults.search_mode = SearchMode::Literal;
+        }
+
+        if is_env_flag_set("GIT_ICASE_PATHSPECS") {
+            defaults.signature |= MagicSignature::ICASE;
+        }
+
+        Ok(defaults)
+    }
+}
+
+fn is_env_flag_set(name: &str) -> bool {
+    match std::env::var_os(name) {
+        // Mirrors `git`'s own `git_env_bool()`: unset *and* explicitly empty both mean "false".
+        Some(value) => !value.is_empty() && value != "0",
+        None => false,
     }
 }
 
@@ -81,7 +147,7 @@ fn parse_short_keywords(input: &[u8], cursor: &mut usize) -> Result<MagicSignatu
     Ok(signature)
 }
 
-fn parse_long_keywords(input: &[u8], cursor: &mut usize) -> Result<Pattern, Error> {
+fn parse_long_keywords(input: &[u8], cursor: &mut usize, base_search_mode: SearchMode) -> Result<Pattern, Error> {
     let end = input.find(")").ok_or(Error::MissingClosingParenthesis {
         pathspec: BString::from(input),
     })?;
@@ -92,16 +158,19 @@ fn parse_long_keywords(input: &[u8], cursor: &mut usize) -> Result<Pattern, Erro
     let mut p = Pattern {
         path: BString::default(),
         signature: MagicSignature::empty(),
-        search_mode: SearchMode::ShellGlob,
+        search_mode: base_search_mode,
         attributes: Vec::new(),
+        prefix: None,
     };
 
-    debug_assert_eq!(p.search_mode, SearchMode::default());
-
     if input.is_empty() {
         return Ok(p);
     }
 
+    // Tracks only the search mode explicitly requested by this pathspec's own magic, so that a
+    // `base_search_mode` coming from the environment doesn't spuriously conflict with it.
+    let mut explicit_search_mode: Option<SearchMode> = None;
+
     let mut keywords = Vec::new();
     let mut i = 0;
     let mut last = 0;
@@ -126,13 +195,13 @@ fn parse_long_keywords(input: &[u8], cursor: &mut usize) -> Result<Pattern, Erro
             b"icase" => p.signature |= MagicSignature::ICASE,
             b"exclude" => p.signature |= MagicSignature::EXCLUDE,
             b"attr" => {}
-            b"literal" => match p.search_mode {
-                SearchMode::PathAwareGlob => return Err(Error::IncompatibleSearchModes),
-                _ => p.search_mode = SearchMode::Literal,
+            b"literal" => match explicit_search_mode {
+                Some(SearchMode::PathAwareGlob) => return Err(Error::IncompatibleSearchModes),
+                _ => explicit_search_mode = Some(SearchMode::Literal),
             },
-            b"glob" => match p.search_mode {
-                SearchMode::Literal => return Err(Error::IncompatibleSearchModes),
-                _ => p.search_mode = SearchMode::PathAwareGlob,
+            b"glob" => match explicit_search_mode {
+                Some(SearchMode::Literal) => return Err(Error::IncompatibleSearchModes),
+                _ => explicit_search_mode = Some(SearchMode::PathAwareGlob),
             },
             _ if keyword.starts_with(b"attr:") => {
                 if p.attributes.is_empty() {
@@ -142,7 +211,7 @@ fn parse_long_keywords(input: &[u8], cursor: &mut usize) -> Result<Pattern, Erro
                 }
             }
             _ if keyword.starts_with(b"prefix:") => {
-                // TODO: Needs research - what does 'prefix:' do
+                p.prefix = Some(parse_prefix(&keyword[b"prefix:".len()..])?);
             }
             _ => {
                 return Err(Error::InvalidKeyword {
@@ -152,21 +221,107 @@ fn parse_long_keywords(input: &[u8], cursor: &mut usize) -> Result<Pattern, Erro
         }
     }
 
+    if let Some(search_mode) = explicit_search_mode {
+        p.search_mode = search_mode;
+    }
+
     Ok(p)
 }
 
+fn parse_prefix(input: &[u8]) -> Result<usize, Error> {
+    input
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| Error::InvalidPrefixValue {
+            found_value: BString::from(input),
+        })
+}
+
 fn parse_attributes(input: &[u8]) -> Result<Vec<(BString, git_attributes::State)>, Error> {
     if input.is_empty() {
         return Err(Error::EmptyAttribute);
     }
-    Iter::new(input.into(), 0)
-        .map(|res| res.map(|(attr, state)| (attr.into(), state.into())))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| match e {
-            git_attributes::parse::Error::AttributeName {
-                line_number: _,
-                attribute,
-            } => Error::InvalidAttribute { attribute },
-            _ => unreachable!("expecting only 'Error::AttributeName' but got {}", e),
-        })
+
+    split_unescaped(input, b' ')
+        .into_iter()
+        .filter(|token| !token.is_empty())
+        .map(parse_attribute)
+        .collect()
+}
+
+fn parse_attribute(token: &[u8]) -> Result<(BString, git_attributes::State), Error> {
+    match find_unescaped(token, b'=') {
+        Some(pos) => {
+            let name = parse_attribute_name(&token[..pos])?;
+            let value = unescape_attribute_value(&token[pos + 1..])?;
+            Ok((name, git_attributes::State::Value(value)))
+        }
+        None => Iter::new(token.into(), 0)
+            .next()
+            .expect("at least one attribute, since `token` is non-empty")
+            .map(|(attr, state)| (attr.into(), state.into()))
+            .map_err(|e| match e {
+                git_attributes::parse::Error::AttributeName {
+                    line_number: _,
+                    attribute,
+                } => Error::InvalidAttribute { attribute },
+                _ => unreachable!("expecting only 'Error::AttributeName' but got {}", e),
+            }),
+    }
+}
+
+fn parse_attribute_name(name: &[u8]) -> Result<BString, Error> {
+    if name.is_empty() || name[0] == b'-' || !name.is_ascii() {
+        return Err(Error::InvalidAttribute {
+            attribute: BString::from(name),
+        });
+    }
+    Ok(BString::from(name))
+}
+
+/// Decode backslash escapes in an `attr:key=value` value, rejecting the unescaped separator
+/// characters `git` itself disallows there.
+fn unescape_attribute_value(input: &[u8]) -> Result<BString, Error> {
+    let mut value = Vec::with_capacity(input.len());
+    let mut bytes = input.iter().copied();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => match bytes.next() {
+                Some(escaped) => value.push(escaped),
+                None => return Err(Error::TrailingEscapeCharacter),
+            },
+            b' ' | b',' => return Err(Error::InvalidAttributeValue { character: b }),
+            _ => value.push(b),
+        }
+    }
+    Ok(value.into())
+}
+
+/// Find the first occurrence of `target` in `input` that isn't preceded by an odd number of `\`.
+fn find_unescaped(input: &[u8], target: u8) -> Option<usize> {
+    let mut escaped = false;
+    for (i, &b) in input.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if b == b'\\' {
+            escaped = true;
+        } else if b == target {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Split `input` on every unescaped occurrence of `separator`, keeping escape sequences intact in
+/// the resulting pieces.
+fn split_unescaped(input: &[u8], separator: u8) -> Vec<&[u8]> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(pos) = find_unescaped(rest, separator) {
+        tokens.push(&rest[..pos]);
+        rest = &rest[pos + 1..];
+    }
+    tokens.push(rest);
+    tokens
 }
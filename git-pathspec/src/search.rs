@@ -0,0 +1,292 @@
+use bstr::{BStr, ByteSlice};
+
+use crate::{MagicSignature, Pattern, SearchMode};
+
+/// A set of [`Pattern`]s to test candidate paths against, honoring `git`'s include/exclude
+/// precedence rules.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Search {
+    patterns: Vec<Pattern>,
+}
+
+/// The outcome of matching a path against a [`Search`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Match<'a> {
+    /// The pattern responsible for this outcome.
+    pub pattern: &'a Pattern,
+    /// Whether `pattern` is an exclude pattern, i.e. carries [`MagicSignature::EXCLUDE`].
+    pub is_exclude: bool,
+}
+
+impl Search {
+    /// Create a new `Search` from `patterns`, evaluated in order.
+    pub fn new(patterns: Vec<Pattern>) -> Self {
+        Search { patterns }
+    }
+
+    /// Match `path` against all patterns in this search, returning the pattern responsible for
+    /// the outcome.
+    ///
+    /// `path` is considered selected if at least one non-exclude pattern matches it and no
+    /// exclude pattern also matches it - exclude patterns take precedence and are reported even
+    /// though they mean `path` is *not* selected, so callers can tell "excluded" apart from
+    /// "untouched by any pattern".
+    ///
+    /// `is_dir` should be `true` if `path` is known to refer to a directory, in which case a
+    /// pattern targeting an entry beneath it is also considered a match, letting callers decide
+    /// whether to descend into it.
+    pub fn matches<'a>(&'a self, path: &BStr, is_dir: bool) -> Option<Match<'a>> {
+        let mut include = None;
+        for pattern in &self.patterns {
+            if !pattern_matches(pattern, path, is_dir) {
+                continue;
+            }
+            if pattern.signature.contains(MagicSignature::EXCLUDE) {
+                return Some(Match { pattern, is_exclude: true });
+            }
+            if include.is_none() {
+                include = Some(pattern);
+            }
+        }
+        include.map(|pattern| Match { pattern, is_exclude: false })
+    }
+}
+
+fn pattern_matches(pattern: &Pattern, path: &BStr, is_dir: bool) -> bool {
+    let icase = pattern.signature.contains(MagicSignature::ICASE);
+
+    // `TOP` anchors the pattern at the repository root, so the directory prefix it was given
+    // relative to (if any) is irrelevant to matching. `prefix` is user-controlled (via
+    // `prefix:<n>`) and isn't validated against `path`'s length at parse time, so it's clamped
+    // here rather than trusted to be in bounds.
+    let spec = if pattern.signature.contains(MagicSignature::TOP) {
+        let start = pattern.prefix.unwrap_or(0).min(pattern.path.len());
+        &pattern.path.as_slice()[start..]
+    } else {
+        pattern.path.as_slice()
+    };
+
+    let candidate = fold_case(path, icase);
+    let spec = fold_case(spec, icase);
+
+    match pattern.search_mode {
+        SearchMode::Literal => literal_matches(&candidate, &spec, is_dir),
+        SearchMode::ShellGlob => glob_matches(&candidate, &spec, is_dir, Glob::Shell),
+        SearchMode::PathAwareGlob => glob_matches(&candidate, &spec, is_dir, Glob::PathAware),
+    }
+}
+
+fn fold_case(input: &[u8], icase: bool) -> Vec<u8> {
+    if icase {
+        input.to_lowercase()
+    } else {
+        input.to_vec()
+    }
+}
+
+fn literal_matches(candidate: &[u8], spec: &[u8], is_dir: bool) -> bool {
+    if candidate == spec {
+        return true;
+    }
+    if let Some(rest) = candidate.strip_prefix(spec) {
+        if rest.first() == Some(&b'/') {
+            return true;
+        }
+    }
+    is_dir && is_ancestor_of(candidate, spec)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Glob {
+    /// `*` and `?` may freely match across path separators, like plain shell globbing.
+    Shell,
+    /// `*` and `?` never cross a path separator, but a standalone `**` path component does.
+    PathAware,
+}
+
+fn glob_matches(candidate: &[u8], spec: &[u8], is_dir: bool, mode: Glob) -> bool {
+    let matches_full = |candidate: &[u8]| match mode {
+        Glob::Shell => fnmatch(spec, candidate),
+        Glob::PathAware => wildmatch(spec, candidate),
+    };
+    // Mirrors `git`'s `match_pathname()`: a pattern also selects anything nested beneath a path it
+    // matches, e.g. pattern `src` selects candidate `src/main.rs`.
+    if matches_any_path_prefix(candidate, matches_full) {
+        return true;
+    }
+    is_dir && is_ancestor_of(candidate, literal_prefix(spec))
+}
+
+/// Whether `is_match` holds for `candidate` as a whole, or for any of its leading path segments.
+fn matches_any_path_prefix(candidate: &[u8], is_match: impl Fn(&[u8]) -> bool) -> bool {
+    if is_match(candidate) {
+        return true;
+    }
+    let mut start = 0;
+    while let Some(pos) = candidate[start..].iter().position(|&b| b == b'/') {
+        let end = start + pos;
+        if is_match(&candidate[..end]) {
+            return true;
+        }
+        start = end + 1;
+    }
+    false
+}
+
+/// Whether `candidate` is a path that could contain further entries matching `spec`, i.e. `spec`
+/// is `candidate` followed by a path separator and more.
+fn is_ancestor_of(candidate: &[u8], spec: &[u8]) -> bool {
+    spec.strip_prefix(candidate)
+        .map_or(false, |rest| rest.first() == Some(&b'/'))
+}
+
+/// The leading portion of `spec` up to its first wildcard character, the only part of a glob
+/// pattern that can be compared to a directory byte-for-byte.
+fn literal_prefix(spec: &[u8]) -> &[u8] {
+    let end = spec
+        .iter()
+        .position(|&b| matches!(b, b'*' | b'?' | b'['))
+        .unwrap_or(spec.len());
+    &spec[..end]
+}
+
+/// Shell-glob matching where `*` matches any run of bytes and `?` matches any single byte, both
+/// freely crossing path separators.
+fn fnmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(&b'*'), _) => fnmatch(&pattern[1..], text) || (!text.is_empty() && fnmatch(pattern, &text[1..])),
+        (Some(&b'?'), Some(_)) => fnmatch(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => fnmatch(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+/// `git`'s pathname-aware wildmatch: `*` and `?` behave like in [`fnmatch()`] but never cross a
+/// `/`, while a `**` path component matches any number of path components, including none.
+fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    let pattern_components: Vec<_> = pattern.split(|&b| b == b'/').collect();
+    let text_components: Vec<_> = text.split(|&b| b == b'/').collect();
+    wildmatch_components(&pattern_components, &text_components)
+}
+
+fn wildmatch_components(pattern: &[&[u8]], text: &[&[u8]]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(&component) if component == b"**".as_slice() => {
+            (0..=text.len()).any(|skip| wildmatch_components(&pattern[1..], &text[skip..]))
+        }
+        Some(&component) => {
+            !text.is_empty() && fnmatch(component, text[0]) && wildmatch_components(&pattern[1..], &text[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(path: &str, search_mode: SearchMode) -> Pattern {
+        Pattern {
+            path: path.into(),
+            signature: MagicSignature::empty(),
+            search_mode,
+            attributes: Vec::new(),
+            prefix: None,
+        }
+    }
+
+    fn is_match(patterns: Vec<Pattern>, path: &str, is_dir: bool) -> Option<bool> {
+        Search::new(patterns)
+            .matches(path.as_bytes().as_bstr(), is_dir)
+            .map(|m| !m.is_exclude)
+    }
+
+    #[test]
+    fn exclude_takes_precedence_over_include() {
+        let mut exclude = pattern("foo", SearchMode::Literal);
+        exclude.signature |= MagicSignature::EXCLUDE;
+        let patterns = vec![pattern("foo", SearchMode::Literal), exclude];
+
+        assert_eq!(is_match(patterns, "foo", false), Some(false));
+    }
+
+    #[test]
+    fn unmatched_path_is_none() {
+        let patterns = vec![pattern("foo", SearchMode::Literal)];
+        assert_eq!(is_match(patterns, "bar", false), None);
+    }
+
+    #[test]
+    fn literal_pattern_selects_files_nested_beneath_it() {
+        let patterns = vec![pattern("src", SearchMode::Literal)];
+        assert_eq!(is_match(patterns, "src/main.rs", false), Some(true));
+    }
+
+    #[test]
+    fn shell_glob_star_crosses_path_separators() {
+        let patterns = vec![pattern("a*b", SearchMode::ShellGlob)];
+        assert_eq!(is_match(patterns, "a/x/b", false), Some(true));
+    }
+
+    #[test]
+    fn shell_glob_pattern_selects_files_nested_beneath_it() {
+        let patterns = vec![pattern("src", SearchMode::ShellGlob)];
+        assert_eq!(is_match(patterns, "src/main.rs", false), Some(true));
+    }
+
+    #[test]
+    fn path_aware_glob_single_star_does_not_cross_path_separators() {
+        let patterns = vec![pattern("a*b", SearchMode::PathAwareGlob)];
+        assert_eq!(is_match(patterns.clone(), "a/x/b", false), None);
+        assert_eq!(is_match(patterns, "axb", false), Some(true));
+    }
+
+    #[test]
+    fn path_aware_glob_double_star_crosses_any_number_of_components() {
+        let patterns = vec![pattern("a/**/b", SearchMode::PathAwareGlob)];
+        assert_eq!(is_match(patterns.clone(), "a/b", false), Some(true));
+        assert_eq!(is_match(patterns, "a/x/y/b", false), Some(true));
+    }
+
+    #[test]
+    fn path_aware_glob_pattern_selects_files_nested_beneath_it() {
+        let patterns = vec![pattern("src", SearchMode::PathAwareGlob)];
+        assert_eq!(is_match(patterns, "src/main.rs", false), Some(true));
+    }
+
+    #[test]
+    fn icase_folds_case_on_both_sides() {
+        let mut icase = pattern("Src", SearchMode::Literal);
+        icase.signature |= MagicSignature::ICASE;
+        assert_eq!(is_match(vec![icase.clone()], "src", false), Some(true));
+
+        icase.signature.remove(MagicSignature::ICASE);
+        assert_eq!(is_match(vec![icase], "src", false), None);
+    }
+
+    #[test]
+    fn top_ignores_the_recorded_subdirectory_prefix() {
+        let mut top = pattern("sub/foo", SearchMode::Literal);
+        top.signature |= MagicSignature::TOP;
+        top.prefix = Some("sub/".len());
+
+        assert_eq!(is_match(vec![top], "foo", false), Some(true));
+    }
+
+    #[test]
+    fn prefix_larger_than_the_path_is_clamped_instead_of_panicking() {
+        let mut top = pattern("a", SearchMode::Literal);
+        top.signature |= MagicSignature::TOP;
+        top.prefix = Some(100);
+
+        assert_eq!(is_match(vec![top], "a", false), None);
+    }
+
+    #[test]
+    fn directory_candidate_matches_a_pattern_targeting_an_entry_beneath_it() {
+        let patterns = vec![pattern("src/main.rs", SearchMode::Literal)];
+        assert_eq!(is_match(patterns.clone(), "src", false), None);
+        assert_eq!(is_match(patterns, "src", true), Some(true));
+    }
+}